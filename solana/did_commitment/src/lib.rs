@@ -3,19 +3,31 @@
 //! The program does not persist state; instead, it provides stateless verification
 //! routines that can be invoked from off-chain clients or CPI callers.  Each
 //! instruction validates that the Merkle proof, aggregate commitment, and
-//! Ed25519 signature match the shared hash commitments produced by the
-//! cross-chain tooling in `packages/core`.
+//! signature match the shared hash commitments produced by the cross-chain
+//! tooling in `packages/core`. Signatures may come from either side of the
+//! bridge: a native Ed25519 key or an Ethereum secp256k1 key recovered via
+//! ecrecover, selected per-instruction by [`SigScheme`]. Ed25519 signatures
+//! may also be checked for free via [`SigScheme::Ed25519Precompile`], which
+//! introspects the runtime's native Ed25519 sig-verify precompile instead of
+//! spending compute on an in-program `ed25519-dalek` verify; callers using
+//! that scheme must pass the instructions sysvar as the first entry of
+//! `accounts`. [`VerifyChainInstruction`] is the one instruction without
+//! Merkle, aggregate, or signature checks: it checks only that a DID
+//! operation hashchain links up to `commitment.did_hash`.
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use ed25519_dalek::{Signature, SigningKey, VerifyingKey, Verifier};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use sha3::{Digest, Keccak256};
 use solana_program::{
     account_info::AccountInfo,
+    ed25519_program,
     entrypoint,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
+    secp256k1_recover::secp256k1_recover,
+    sysvar::instructions::load_instruction_at_checked,
 };
 use thiserror::Error;
 
@@ -40,14 +52,145 @@ impl CommitmentTuple {
     }
 }
 
+/// Which key type signed the aggregate commitment.
+///
+/// The registry mirrors an Ethereum DID registry whose signers hold
+/// secp256k1 keys, so both native Ed25519 signers and Ethereum-style
+/// ecrecover signers are accepted.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SigScheme {
+    Ed25519 {
+        pubkey: [u8; 32],
+        sig: [u8; 64],
+    },
+    Secp256k1Recover {
+        sig: [u8; 64],
+        recovery_id: u8,
+        expected_address: [u8; 20],
+    },
+    /// Verified by introspecting a sibling Ed25519 sig-verify precompile
+    /// instruction in the same transaction instead of checking the
+    /// signature in-program.
+    Ed25519Precompile {
+        pubkey: [u8; 32],
+        sig: [u8; 64],
+    },
+}
+
+/// Half of the secp256k1 curve order `n`, the upper bound for a
+/// non-malleable (low-S) signature.
+const SECP256K1_HALF_ORDER: Hash = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct VerifyInstruction {
     pub commitment: CommitmentTuple,
     pub credential_leaf: Hash,
     pub index: u64,
     pub merkle_proof: Vec<Hash>,
-    pub ed25519_public_key: [u8; 32],
-    pub ed25519_signature: [u8; 64],
+    pub sig_scheme: SigScheme,
+}
+
+/// Verify many [`VerifyInstruction`]s in a single CPI so off-chain tooling can
+/// amortize one call over a whole credential set.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyBatchInstruction {
+    pub items: Vec<VerifyInstruction>,
+}
+
+/// Proof of inclusion for a leaf in a Merkle Mountain Range: an append-only
+/// accumulator where a new leaf either starts a fresh peak or, when the two
+/// rightmost peaks have equal height, merges upward
+/// (`parent = Keccak256(left || right)`) until heights differ again.
+///
+/// A proof is the authentication path within the leaf's own peak
+/// (`local_path`) plus the hashes of every other peak (`peak_hashes`),
+/// needed to "bag the peaks" into the final root.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MmrProof {
+    pub local_path: Vec<Hash>,
+    pub peak_hashes: Vec<Hash>,
+    pub pos: u64,
+    pub size: u64,
+}
+
+/// Verifies a credential against an append-only MMR `credential_root`
+/// instead of the fixed positional Merkle tree used by [`VerifyInstruction`].
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyMmrInstruction {
+    pub commitment: CommitmentTuple,
+    pub credential_leaf: Hash,
+    pub mmr_proof: MmrProof,
+    pub sig_scheme: SigScheme,
+}
+
+/// Verifies that an aggregate commitment was attested by a threshold of
+/// guardians rather than a single signer, for cross-chain attestations that
+/// require M-of-N agreement.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuorumVerifyInstruction {
+    pub commitment: CommitmentTuple,
+    pub credential_leaf: Hash,
+    pub index: u64,
+    pub merkle_proof: Vec<Hash>,
+    pub guardian_set: Vec<[u8; 32]>,
+    pub guardian_set_hash: Hash,
+    /// `(guardian_index, ed25519_sig)` pairs, ordered by strictly increasing
+    /// `guardian_index` to rule out duplicate signers.
+    pub signatures: Vec<(u8, [u8; 64])>,
+}
+
+/// One link in an append-only DID operation hashchain: `operation_hash`
+/// commits to the operation itself, and `previous_hash` chains back to the
+/// entry before it so tampering anywhere invalidates every entry after it.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HashchainEntry {
+    pub operation_hash: Hash,
+    pub previous_hash: Hash,
+}
+
+/// Verifies that `entries` forms a valid hashchain whose head matches both
+/// `expected_head` and `commitment.did_hash`, giving stateless on-chain
+/// auditing of DID update history produced by the off-chain tooling.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyChainInstruction {
+    pub commitment: CommitmentTuple,
+    pub entries: Vec<HashchainEntry>,
+    pub expected_head: Hash,
+}
+
+/// Maximum number of items accepted by [`VerifyBatchInstruction`], chosen to
+/// keep the worst case (all Ed25519 verifies) within a single transaction's
+/// compute budget.
+pub const MAX_BATCH_SIZE: usize = 32;
+
+/// Leading byte of `instruction_data` that tells [`process_instruction`] how
+/// to interpret the remainder.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum InstructionTag {
+    Verify = 0,
+    VerifyBatch = 1,
+    VerifyMmr = 2,
+    VerifyQuorum = 3,
+    VerifyChain = 4,
+}
+
+impl TryFrom<u8> for InstructionTag {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InstructionTag::Verify),
+            1 => Ok(InstructionTag::VerifyBatch),
+            2 => Ok(InstructionTag::VerifyMmr),
+            3 => Ok(InstructionTag::VerifyQuorum),
+            4 => Ok(InstructionTag::VerifyChain),
+            _ => Err(DidCommitmentError::DeserializeFailed.into()),
+        }
+    }
 }
 
 #[derive(Error, Debug, Copy, Clone)]
@@ -60,6 +203,30 @@ pub enum DidCommitmentError {
     SignatureInvalid,
     #[error("instruction deserialize error")]
     DeserializeFailed,
+    #[error("batch exceeds maximum size")]
+    BatchTooLarge,
+    #[error("invalid secp256k1 recovery id")]
+    InvalidRecoveryId,
+    #[error("secp256k1 signature is malleable (high-S)")]
+    MalleableSignature,
+    #[error("recovered address does not match expected address")]
+    AddressMismatch,
+    #[error("mmr root invalid")]
+    InvalidMmrProof,
+    #[error("guardian set hash mismatch")]
+    GuardianSetMismatch,
+    #[error("guardian signature indices must be strictly increasing")]
+    UnorderedGuardianIndex,
+    #[error("guardian index out of range")]
+    GuardianIndexOutOfRange,
+    #[error("quorum of guardian signatures not met")]
+    QuorumNotMet,
+    #[error("hashchain entry does not link to the previous entry")]
+    BrokenHashchain,
+    #[error("hashchain head does not match expected head")]
+    HashchainHeadMismatch,
+    #[error("missing instructions sysvar account for precompile introspection")]
+    MissingInstructionsSysvar,
 }
 
 impl From<DidCommitmentError> for ProgramError {
@@ -72,42 +239,497 @@ entrypoint!(process_instruction);
 
 pub fn process_instruction(
     _program_id: &Pubkey,
-    _accounts: &[AccountInfo],
+    accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    let instruction = VerifyInstruction::try_from_slice(instruction_data)
-        .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+    let (tag_byte, rest) = instruction_data
+        .split_first()
+        .ok_or_else(|| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
 
-    verify_commitment(&instruction)?;
-    Ok(())
-}
-
-pub fn verify_commitment(instruction: &VerifyInstruction) -> Result<(), ProgramError> {
-    let recomputed = instruction.commitment.recompute_aggregate();
-    if recomputed != instruction.commitment.aggregate_commitment {
-        msg!("aggregate mismatch: expected {:?} got {:?}", instruction.commitment.aggregate_commitment, recomputed);
-        return Err(DidCommitmentError::AggregateMismatch.into());
+    match InstructionTag::try_from(*tag_byte)? {
+        InstructionTag::Verify => {
+            let instruction = VerifyInstruction::try_from_slice(rest)
+                .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+            verify_commitment(&instruction, accounts)
+        }
+        InstructionTag::VerifyBatch => {
+            let instruction = VerifyBatchInstruction::try_from_slice(rest)
+                .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+            verify_commitment_batch(&instruction, accounts)
+        }
+        InstructionTag::VerifyMmr => {
+            let instruction = VerifyMmrInstruction::try_from_slice(rest)
+                .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+            verify_mmr_commitment(&instruction, accounts)
+        }
+        InstructionTag::VerifyQuorum => {
+            let instruction = QuorumVerifyInstruction::try_from_slice(rest)
+                .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+            verify_quorum_commitment(&instruction)
+        }
+        InstructionTag::VerifyChain => {
+            let instruction = VerifyChainInstruction::try_from_slice(rest)
+                .map_err(|_| ProgramError::from(DidCommitmentError::DeserializeFailed))?;
+            verify_hashchain(&instruction)
+        }
     }
+}
 
-    let leaf_root = compute_merkle_root(
+pub fn verify_commitment(
+    instruction: &VerifyInstruction,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    verify_aggregate(&instruction.commitment)?;
+    verify_merkle_inclusion(
         instruction.credential_leaf,
         &instruction.merkle_proof,
         instruction.index,
-    );
+        instruction.commitment.credential_root,
+    )?;
+
+    verify_signature(
+        &instruction.sig_scheme,
+        &instruction.commitment.aggregate_commitment,
+        accounts,
+    )
+}
 
-    if leaf_root != instruction.commitment.credential_root {
+/// Check that `commitment.aggregate_commitment` matches the hash of its
+/// constituent fields, shared by every instruction variant except
+/// [`VerifyChainInstruction`].
+fn verify_aggregate(commitment: &CommitmentTuple) -> Result<(), ProgramError> {
+    let recomputed = commitment.recompute_aggregate();
+    if recomputed != commitment.aggregate_commitment {
+        msg!(
+            "aggregate mismatch: expected {:?} got {:?}",
+            commitment.aggregate_commitment,
+            recomputed
+        );
+        return Err(DidCommitmentError::AggregateMismatch.into());
+    }
+    Ok(())
+}
+
+/// Check that `leaf` is included in `credential_root` at `index` via the
+/// fixed positional Merkle proof `proof`, shared by the instruction variants
+/// that address credentials by index rather than by MMR position.
+fn verify_merkle_inclusion(
+    leaf: Hash,
+    proof: &[Hash],
+    index: u64,
+    credential_root: Hash,
+) -> Result<(), ProgramError> {
+    let leaf_root = compute_merkle_root(leaf, proof, index);
+    if leaf_root != credential_root {
         msg!("invalid merkle proof");
         return Err(DidCommitmentError::InvalidMerkleProof.into());
     }
+    Ok(())
+}
+
+/// Check `sig_scheme` over `message`, dispatching to the Ed25519,
+/// secp256k1-ecrecover, or Ed25519-precompile path as appropriate. `accounts`
+/// is only consulted by [`SigScheme::Ed25519Precompile`], which expects the
+/// instructions sysvar at `accounts[0]`.
+fn verify_signature(
+    sig_scheme: &SigScheme,
+    message: &Hash,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    match sig_scheme {
+        SigScheme::Ed25519 { pubkey, sig } => {
+            let verifying_key =
+                VerifyingKey::from_bytes(pubkey).map_err(|_| DidCommitmentError::SignatureInvalid)?;
+            let signature = Signature::from_bytes(sig);
+
+            verifying_key
+                .verify_strict(message, &signature)
+                .map_err(|_| DidCommitmentError::SignatureInvalid.into())
+        }
+        SigScheme::Secp256k1Recover {
+            sig,
+            recovery_id,
+            expected_address,
+        } => check_secp256k1_recover(message, sig, *recovery_id, expected_address),
+        SigScheme::Ed25519Precompile { pubkey, sig } => {
+            let instruction_sysvar = accounts
+                .first()
+                .ok_or(DidCommitmentError::MissingInstructionsSysvar)?;
+            check_ed25519_precompile(instruction_sysvar, message, pubkey, sig)
+        }
+    }
+}
 
-    let verifying_key = VerifyingKey::from_bytes(&instruction.ed25519_public_key)
-        .map_err(|_| DidCommitmentError::SignatureInvalid)?;
-    let signature = Signature::from_bytes(&instruction.ed25519_signature)
+/// Confirm that the current transaction carries a sibling Ed25519 sig-verify
+/// precompile instruction attesting to `expected_msg`, `expected_pubkey`, and
+/// `expected_sig`, by introspecting the instructions sysvar rather than
+/// re-verifying the signature in-program.
+fn check_ed25519_precompile(
+    instruction_sysvar: &AccountInfo,
+    expected_msg: &Hash,
+    expected_pubkey: &[u8; 32],
+    expected_sig: &[u8; 64],
+) -> Result<(), ProgramError> {
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instruction_sysvar) {
+        index += 1;
+
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        if ed25519_instruction_matches(&ix.data, expected_msg, expected_pubkey, expected_sig) {
+            return Ok(());
+        }
+    }
+
+    msg!("no matching ed25519 precompile instruction found");
+    Err(DidCommitmentError::SignatureInvalid.into())
+}
+
+/// Parse a single-signature Ed25519 precompile instruction's offsets header
+/// and check that the signature, public key, and message it points at match
+/// the expected values. Layout per the native Ed25519 program: a 2-byte
+/// header (`num_signatures`, padding) followed by one 14-byte
+/// `Ed25519SignatureOffsets` struct, then the referenced data itself.
+fn ed25519_instruction_matches(
+    data: &[u8],
+    expected_msg: &Hash,
+    expected_pubkey: &[u8; 32],
+    expected_sig: &[u8; 64],
+) -> bool {
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    /// Sentinel meaning "this same instruction", per `Ed25519SignatureOffsets`.
+    const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+    if data.len() < HEADER_LEN + OFFSETS_LEN || data[0] != 1 {
+        return false;
+    }
+
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+
+    // The three `*_instruction_index` fields tell the runtime which sibling
+    // instruction to actually pull the signature/pubkey/message from when
+    // verifying. Only `CURRENT_INSTRUCTION` means "this instruction's own
+    // data", which is the only case where reading offsets out of `data`
+    // below reflects what the runtime cryptographically verified. Without
+    // this check a forger could plant bytes matching our expectations in
+    // this instruction's data while pointing the actual verified signature
+    // at a different, unrelated instruction.
+    if read_u16(4) != CURRENT_INSTRUCTION
+        || read_u16(8) != CURRENT_INSTRUCTION
+        || read_u16(14) != CURRENT_INSTRUCTION
+    {
+        return false;
+    }
+
+    let signature_offset = read_u16(2) as usize;
+    let public_key_offset = read_u16(6) as usize;
+    let message_data_offset = read_u16(10) as usize;
+    let message_data_size = read_u16(12) as usize;
+
+    let Some(sig_end) = signature_offset.checked_add(64) else { return false };
+    let Some(pubkey_end) = public_key_offset.checked_add(32) else { return false };
+    let Some(msg_end) = message_data_offset.checked_add(message_data_size) else { return false };
+
+    if sig_end > data.len() || pubkey_end > data.len() || msg_end > data.len() {
+        return false;
+    }
+
+    &data[signature_offset..sig_end] == expected_sig
+        && &data[public_key_offset..pubkey_end] == expected_pubkey
+        && data[message_data_offset..msg_end] == *expected_msg
+}
+
+/// Recover an Ethereum address from a secp256k1 signature over
+/// `Keccak256(message)` and require it to match `expected_address`.
+///
+/// Rejects invalid recovery ids (only `0..=3` are meaningful) and
+/// high-S signatures, which are malleable: a third party could flip `s` to
+/// `n - s` and `v` accordingly to produce a second, equally valid signature
+/// over the same message.
+fn check_secp256k1_recover(
+    message: &Hash,
+    sig: &[u8; 64],
+    recovery_id: u8,
+    expected_address: &[u8; 20],
+) -> Result<(), ProgramError> {
+    if recovery_id > 3 {
+        msg!("invalid secp256k1 recovery id: {}", recovery_id);
+        return Err(DidCommitmentError::InvalidRecoveryId.into());
+    }
+
+    if sig[32..] > SECP256K1_HALF_ORDER[..] {
+        msg!("rejecting high-S secp256k1 signature");
+        return Err(DidCommitmentError::MalleableSignature.into());
+    }
+
+    let hash = Keccak256::digest(message);
+    let recovered = secp256k1_recover(&hash, recovery_id, sig)
         .map_err(|_| DidCommitmentError::SignatureInvalid)?;
 
-    verifying_key
-        .verify_strict(&instruction.commitment.aggregate_commitment, &signature)
-        .map_err(|_| DidCommitmentError::SignatureInvalid.into())
+    let address_hash = Keccak256::digest(recovered.to_bytes());
+    let recovered_address = &address_hash[12..];
+
+    if recovered_address != expected_address {
+        msg!(
+            "recovered address {:?} does not match expected {:?}",
+            recovered_address,
+            expected_address
+        );
+        return Err(DidCommitmentError::AddressMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// Verify every item in `instruction.items`, failing on the first bad entry.
+///
+/// Items are checked in order, and each item's checks run cheapest-first
+/// (aggregate recompute, then Merkle, then the costly signature verify) so a
+/// malformed item short-circuits before paying for its own signature
+/// verification; items before it have already been fully verified. The
+/// failing index is logged so the
+/// off-chain caller can pinpoint which credential needs re-submission.
+pub fn verify_commitment_batch(
+    instruction: &VerifyBatchInstruction,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    if instruction.items.len() > MAX_BATCH_SIZE {
+        msg!(
+            "batch of {} items exceeds max of {}",
+            instruction.items.len(),
+            MAX_BATCH_SIZE
+        );
+        return Err(DidCommitmentError::BatchTooLarge.into());
+    }
+
+    for (i, item) in instruction.items.iter().enumerate() {
+        verify_commitment(item, accounts).map_err(|err| {
+            msg!("batch item {} failed verification", i);
+            err
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Checks `instruction` against an append-only MMR `credential_root` rather
+/// than the fixed positional Merkle tree used by [`verify_commitment`].
+pub fn verify_mmr_commitment(
+    instruction: &VerifyMmrInstruction,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    verify_aggregate(&instruction.commitment)?;
+
+    let mmr_root = compute_mmr_root(
+        instruction.credential_leaf,
+        &instruction.mmr_proof.local_path,
+        &instruction.mmr_proof.peak_hashes,
+        instruction.mmr_proof.pos,
+        instruction.mmr_proof.size,
+    )?;
+
+    if mmr_root != instruction.commitment.credential_root {
+        msg!("invalid mmr proof");
+        return Err(DidCommitmentError::InvalidMmrProof.into());
+    }
+
+    verify_signature(
+        &instruction.sig_scheme,
+        &instruction.commitment.aggregate_commitment,
+        accounts,
+    )
+}
+
+/// Checks that `instruction.commitment.aggregate_commitment` was attested by
+/// a quorum of `floor(2*n/3)+1` guardians out of the pinned guardian set,
+/// in addition to the usual aggregate and Merkle checks.
+pub fn verify_quorum_commitment(instruction: &QuorumVerifyInstruction) -> Result<(), ProgramError> {
+    verify_aggregate(&instruction.commitment)?;
+    verify_merkle_inclusion(
+        instruction.credential_leaf,
+        &instruction.merkle_proof,
+        instruction.index,
+        instruction.commitment.credential_root,
+    )?;
+
+    let mut hasher = Keccak256::new();
+    for guardian in &instruction.guardian_set {
+        hasher.update(guardian);
+    }
+    let computed_set_hash: Hash = hasher.finalize().into();
+    if computed_set_hash != instruction.guardian_set_hash {
+        msg!("guardian set hash mismatch");
+        return Err(DidCommitmentError::GuardianSetMismatch.into());
+    }
+
+    let n = instruction.guardian_set.len();
+    let quorum = 2 * n / 3 + 1;
+
+    let mut last_index: Option<u8> = None;
+    for (guardian_index, sig) in &instruction.signatures {
+        if let Some(last) = last_index {
+            if *guardian_index <= last {
+                msg!("guardian indices must be strictly increasing");
+                return Err(DidCommitmentError::UnorderedGuardianIndex.into());
+            }
+        }
+        last_index = Some(*guardian_index);
+
+        let guardian_pubkey = instruction
+            .guardian_set
+            .get(*guardian_index as usize)
+            .ok_or(DidCommitmentError::GuardianIndexOutOfRange)?;
+
+        let verifying_key = VerifyingKey::from_bytes(guardian_pubkey)
+            .map_err(|_| DidCommitmentError::SignatureInvalid)?;
+        let signature = Signature::from_bytes(sig);
+
+        verifying_key
+            .verify_strict(&instruction.commitment.aggregate_commitment, &signature)
+            .map_err(|_| DidCommitmentError::SignatureInvalid)?;
+    }
+
+    if instruction.signatures.len() < quorum {
+        msg!(
+            "quorum not met: {} of {} signatures, need {}",
+            instruction.signatures.len(),
+            n,
+            quorum
+        );
+        return Err(DidCommitmentError::QuorumNotMet.into());
+    }
+
+    Ok(())
+}
+
+/// Genesis entry's `previous_hash`, since there is no entry before it.
+const HASHCHAIN_GENESIS_HASH: Hash = [0u8; 32];
+
+/// Folds `instruction.entries` into a single head hash, checking that each
+/// entry links to the one before it, and that the resulting head matches
+/// both `instruction.expected_head` and `instruction.commitment.did_hash`.
+pub fn verify_hashchain(instruction: &VerifyChainInstruction) -> Result<(), ProgramError> {
+    let mut expected_previous = HASHCHAIN_GENESIS_HASH;
+    let mut computed_head = HASHCHAIN_GENESIS_HASH;
+
+    for (i, entry) in instruction.entries.iter().enumerate() {
+        if entry.previous_hash != expected_previous {
+            msg!("hashchain entry {} does not link to the previous entry", i);
+            return Err(DidCommitmentError::BrokenHashchain.into());
+        }
+
+        let mut hasher = Keccak256::new();
+        hasher.update(entry.previous_hash);
+        hasher.update(entry.operation_hash);
+        computed_head = hasher.finalize().into();
+
+        expected_previous = computed_head;
+    }
+
+    if computed_head != instruction.expected_head || computed_head != instruction.commitment.did_hash {
+        msg!("hashchain head mismatch");
+        return Err(DidCommitmentError::HashchainHeadMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// Leaf counts of each peak in an MMR of `size` leaves, ordered left
+/// (tallest) to right (shortest) — one peak per set bit of `size`, from
+/// the most significant bit down.
+fn mmr_peak_leaf_counts(size: u64) -> Vec<u64> {
+    let mut counts = Vec::new();
+    for bit in (0..u64::BITS).rev() {
+        let count = 1u64 << bit;
+        if size & count != 0 {
+            counts.push(count);
+        }
+    }
+    counts
+}
+
+/// Recompute an MMR root from a leaf, its local authentication path within
+/// its own peak, and the hashes of the other peaks, per the leaf's position
+/// `pos` among `size` total leaves.
+///
+/// Rejects `pos >= size` (no such leaf exists) and a `peak_hashes` whose
+/// length doesn't match the number of peaks other than the leaf's own, since
+/// either would otherwise let a malformed proof silently fold into some
+/// root instead of being caught before the final comparison.
+pub fn compute_mmr_root(
+    leaf: Hash,
+    local_path: &[Hash],
+    peak_hashes: &[Hash],
+    pos: u64,
+    size: u64,
+) -> Result<Hash, ProgramError> {
+    if pos >= size {
+        msg!("mmr proof position {} out of range for size {}", pos, size);
+        return Err(DidCommitmentError::InvalidMmrProof.into());
+    }
+
+    let peak_leaf_counts = mmr_peak_leaf_counts(size);
+
+    if peak_hashes.len() != peak_leaf_counts.len() - 1 {
+        msg!(
+            "mmr proof has {} peak hashes, expected {}",
+            peak_hashes.len(),
+            peak_leaf_counts.len() - 1
+        );
+        return Err(DidCommitmentError::InvalidMmrProof.into());
+    }
+
+    let mut start = 0u64;
+    let mut our_peak_index = 0usize;
+    let mut local_index = 0u64;
+    for (i, &count) in peak_leaf_counts.iter().enumerate() {
+        if pos < start + count {
+            our_peak_index = i;
+            local_index = pos - start;
+            break;
+        }
+        start += count;
+    }
+
+    let mut computed = leaf;
+    let mut idx = local_index;
+    for sibling in local_path {
+        let mut hasher = Keccak256::new();
+        if idx % 2 == 0 {
+            hasher.update(computed);
+            hasher.update(sibling);
+        } else {
+            hasher.update(sibling);
+            hasher.update(computed);
+        }
+        computed = hasher.finalize().into();
+        idx /= 2;
+    }
+
+    let mut other_peaks = peak_hashes.iter();
+    let peaks: Vec<Hash> = (0..peak_leaf_counts.len())
+        .map(|i| {
+            if i == our_peak_index {
+                computed
+            } else {
+                other_peaks.next().copied().unwrap_or_default()
+            }
+        })
+        .collect();
+
+    // Bag the peaks right-to-left into a single root.
+    let mut iter = peaks.into_iter().rev();
+    let mut bagged = iter.next().unwrap_or_default();
+    for peak in iter {
+        let mut hasher = Keccak256::new();
+        hasher.update(peak);
+        hasher.update(bagged);
+        bagged = hasher.finalize().into();
+    }
+    Ok(bagged)
 }
 
 fn compute_merkle_root(leaf: Hash, proof: &[Hash], mut index: u64) -> Hash {
@@ -131,3 +753,298 @@ fn compute_merkle_root(leaf: Hash, proof: &[Hash], mut index: u64) -> Hash {
 pub fn sign_commitment(commitment: &CommitmentTuple, signing_key: &SigningKey) -> [u8; 64] {
     signing_key.sign(&commitment.aggregate_commitment).to_bytes()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a well-formed single-signature Ed25519 precompile instruction
+    /// buffer, with every `*_instruction_index` field set to `this_index`
+    /// (use `u16::MAX` for "this same instruction").
+    fn build_ed25519_precompile_data(
+        msg: &Hash,
+        pubkey: &[u8; 32],
+        sig: &[u8; 64],
+        this_index: u16,
+    ) -> Vec<u8> {
+        let signature_offset = 16u16;
+        let public_key_offset = signature_offset + 64;
+        let message_data_offset = public_key_offset + 32;
+        let message_data_size = msg.len() as u16;
+
+        let mut data = vec![0u8; message_data_offset as usize + message_data_size as usize];
+        data[0] = 1; // num_signatures
+        data[1] = 0; // padding
+        data[2..4].copy_from_slice(&signature_offset.to_le_bytes());
+        data[4..6].copy_from_slice(&this_index.to_le_bytes());
+        data[6..8].copy_from_slice(&public_key_offset.to_le_bytes());
+        data[8..10].copy_from_slice(&this_index.to_le_bytes());
+        data[10..12].copy_from_slice(&message_data_offset.to_le_bytes());
+        data[12..14].copy_from_slice(&message_data_size.to_le_bytes());
+        data[14..16].copy_from_slice(&this_index.to_le_bytes());
+        data[signature_offset as usize..public_key_offset as usize].copy_from_slice(sig);
+        data[public_key_offset as usize..message_data_offset as usize].copy_from_slice(pubkey);
+        data[message_data_offset as usize..].copy_from_slice(msg);
+        data
+    }
+
+    #[test]
+    fn ed25519_precompile_matches_current_instruction() {
+        let msg = [7u8; 32];
+        let pubkey = [2u8; 32];
+        let sig = [3u8; 64];
+        let data = build_ed25519_precompile_data(&msg, &pubkey, &sig, u16::MAX);
+        assert!(ed25519_instruction_matches(&data, &msg, &pubkey, &sig));
+    }
+
+    /// Regression test: a forged precompile instruction that embeds the
+    /// expected signature/pubkey/message directly in its own data, but
+    /// points every `*_instruction_index` field at a different instruction
+    /// (index 0), must be rejected. Otherwise the bytes we read never have
+    /// to be the bytes the runtime actually verified.
+    #[test]
+    fn ed25519_precompile_rejects_mismatched_instruction_index() {
+        let msg = [7u8; 32];
+        let pubkey = [2u8; 32];
+        let sig = [3u8; 64];
+        let data = build_ed25519_precompile_data(&msg, &pubkey, &sig, 0);
+        assert!(!ed25519_instruction_matches(&data, &msg, &pubkey, &sig));
+    }
+
+    fn keccak_concat(a: Hash, b: Hash) -> Hash {
+        let mut hasher = Keccak256::new();
+        hasher.update(a);
+        hasher.update(b);
+        hasher.finalize().into()
+    }
+
+    #[test]
+    fn mmr_root_round_trips_for_perfect_subtree() {
+        let h0 = [0u8; 32];
+        let h1 = [1u8; 32];
+        let h2 = [2u8; 32];
+        let h3 = [3u8; 32];
+        let h01 = keccak_concat(h0, h1);
+        let h23 = keccak_concat(h2, h3);
+        let root = keccak_concat(h01, h23);
+
+        let got = compute_mmr_root(h1, &[h0, h23], &[], 1, 4).unwrap();
+        assert_eq!(got, root);
+    }
+
+    #[test]
+    fn mmr_root_rejects_position_out_of_range() {
+        assert!(compute_mmr_root([1u8; 32], &[], &[], 4, 4).is_err());
+    }
+
+    #[test]
+    fn mmr_root_rejects_wrong_peak_hash_count() {
+        // size = 5 has two peaks (counts [4, 1]), so a leaf in the
+        // second peak expects exactly one other peak hash, not zero.
+        assert!(compute_mmr_root([1u8; 32], &[], &[], 4, 5).is_err());
+    }
+
+    fn trivial_commitment(did_hash: Hash, credential_root: Hash) -> CommitmentTuple {
+        let mut commitment = CommitmentTuple {
+            did_hash,
+            credential_root,
+            zk_commitment: [9u8; 32],
+            aggregate_commitment: [0u8; 32],
+        };
+        commitment.aggregate_commitment = commitment.recompute_aggregate();
+        commitment
+    }
+
+    fn test_signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[1u8; 32])
+    }
+
+    #[test]
+    fn verify_commitment_batch_fails_fast_on_bad_item() {
+        let signing_key = test_signing_key();
+        let pubkey = signing_key.verifying_key().to_bytes();
+        let leaf = [5u8; 32];
+        let commitment = trivial_commitment([4u8; 32], leaf);
+        let good_sig = sign_commitment(&commitment, &signing_key);
+
+        let good_item = VerifyInstruction {
+            commitment: commitment.clone(),
+            credential_leaf: leaf,
+            index: 0,
+            merkle_proof: vec![],
+            sig_scheme: SigScheme::Ed25519 {
+                pubkey,
+                sig: good_sig,
+            },
+        };
+        let mut bad_item = good_item.clone();
+        bad_item.sig_scheme = SigScheme::Ed25519 {
+            pubkey,
+            sig: [0u8; 64],
+        };
+
+        let accounts: [AccountInfo; 0] = [];
+        assert!(verify_commitment_batch(
+            &VerifyBatchInstruction {
+                items: vec![good_item.clone(), good_item.clone()],
+            },
+            &accounts,
+        )
+        .is_ok());
+
+        assert!(verify_commitment_batch(
+            &VerifyBatchInstruction {
+                items: vec![good_item, bad_item],
+            },
+            &accounts,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn verify_commitment_batch_rejects_over_max_size() {
+        let signing_key = test_signing_key();
+        let pubkey = signing_key.verifying_key().to_bytes();
+        let leaf = [5u8; 32];
+        let commitment = trivial_commitment([4u8; 32], leaf);
+        let sig = sign_commitment(&commitment, &signing_key);
+
+        let item = VerifyInstruction {
+            commitment,
+            credential_leaf: leaf,
+            index: 0,
+            merkle_proof: vec![],
+            sig_scheme: SigScheme::Ed25519 { pubkey, sig },
+        };
+
+        let accounts: [AccountInfo; 0] = [];
+        let err = verify_commitment_batch(
+            &VerifyBatchInstruction {
+                items: vec![item; MAX_BATCH_SIZE + 1],
+            },
+            &accounts,
+        )
+        .unwrap_err();
+        assert_eq!(err, DidCommitmentError::BatchTooLarge.into());
+    }
+
+    #[test]
+    fn quorum_commitment_requires_threshold_of_signatures() {
+        let guardians: Vec<SigningKey> = (0..4u8)
+            .map(|i| SigningKey::from_bytes(&[i + 1; 32]))
+            .collect();
+        let guardian_set: Vec<[u8; 32]> =
+            guardians.iter().map(|k| k.verifying_key().to_bytes()).collect();
+
+        let mut hasher = Keccak256::new();
+        for guardian in &guardian_set {
+            hasher.update(guardian);
+        }
+        let guardian_set_hash: Hash = hasher.finalize().into();
+
+        let leaf = [6u8; 32];
+        let commitment = trivial_commitment([4u8; 32], leaf);
+
+        let sign = |i: usize| -> (u8, [u8; 64]) {
+            (i as u8, sign_commitment(&commitment, &guardians[i]))
+        };
+
+        let instruction = |signatures: Vec<(u8, [u8; 64])>| QuorumVerifyInstruction {
+            commitment: commitment.clone(),
+            credential_leaf: leaf,
+            index: 0,
+            merkle_proof: vec![],
+            guardian_set: guardian_set.clone(),
+            guardian_set_hash,
+            signatures,
+        };
+
+        // quorum for n=4 is floor(8/3)+1 = 3.
+        assert!(verify_quorum_commitment(&instruction(vec![sign(0), sign(1), sign(2)])).is_ok());
+        assert!(verify_quorum_commitment(&instruction(vec![sign(0), sign(1)])).is_err());
+    }
+
+    #[test]
+    fn hashchain_rejects_broken_link() {
+        let op1 = [1u8; 32];
+        let op2 = [2u8; 32];
+
+        let entry1_hash = keccak_concat(HASHCHAIN_GENESIS_HASH, op1);
+        let entry2_hash = keccak_concat(entry1_hash, op2);
+
+        let commitment = trivial_commitment(entry2_hash, [9u8; 32]);
+
+        let good = VerifyChainInstruction {
+            commitment: commitment.clone(),
+            entries: vec![
+                HashchainEntry {
+                    operation_hash: op1,
+                    previous_hash: HASHCHAIN_GENESIS_HASH,
+                },
+                HashchainEntry {
+                    operation_hash: op2,
+                    previous_hash: entry1_hash,
+                },
+            ],
+            expected_head: entry2_hash,
+        };
+        assert!(verify_hashchain(&good).is_ok());
+
+        let mut broken = good.clone();
+        broken.entries[1].previous_hash = [0xff; 32];
+        assert!(verify_hashchain(&broken).is_err());
+    }
+
+    /// Derives the Ethereum-style address (last 20 bytes of
+    /// `Keccak256(uncompressed_pubkey)`, dropping the `0x04` prefix) that
+    /// `check_secp256k1_recover` expects a recovered key to match.
+    fn eth_address(verifying_key: &k256::ecdsa::VerifyingKey) -> [u8; 20] {
+        let encoded = verifying_key.to_encoded_point(false);
+        let address_hash = Keccak256::digest(&encoded.as_bytes()[1..]);
+        address_hash[12..].try_into().unwrap()
+    }
+
+    #[test]
+    fn secp256k1_recover_accepts_valid_signature_and_rejects_forgeries() {
+        use k256::ecdsa::{RecoveryId, Signature as K256Signature, SigningKey as K256SigningKey};
+
+        let signing_key = K256SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+        let expected_address = eth_address(signing_key.verifying_key());
+
+        let message = [9u8; 32];
+        let digest = Keccak256::new_with_prefix(message);
+        let (signature, recid) = signing_key.sign_digest_recoverable(digest).unwrap();
+
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&signature.to_bytes());
+        let recovery_id = recid.to_byte();
+
+        assert!(check_secp256k1_recover(&message, &sig, recovery_id, &expected_address).is_ok());
+
+        let mut wrong_address = expected_address;
+        wrong_address[0] ^= 0xff;
+        assert!(check_secp256k1_recover(&message, &sig, recovery_id, &wrong_address).is_err());
+
+        assert!(check_secp256k1_recover(&message, &sig, 4, &expected_address).is_err());
+
+        // The malleable twin: negating `s` to `n - s` still recovers the
+        // same key if the recovery id's y-parity bit is flipped too, so it
+        // must be rejected on the high-S check alone.
+        let malleable_s = -signature.s();
+        let malleable_sig = K256Signature::from_scalars(signature.r(), malleable_s).unwrap();
+        let mut malleable_bytes = [0u8; 64];
+        malleable_bytes.copy_from_slice(&malleable_sig.to_bytes());
+        let malleable_recovery_id = recovery_id ^ 1;
+        assert_eq!(
+            RecoveryId::from_byte(malleable_recovery_id).unwrap().is_y_odd(),
+            !recid.is_y_odd()
+        );
+        assert!(check_secp256k1_recover(
+            &message,
+            &malleable_bytes,
+            malleable_recovery_id,
+            &expected_address
+        )
+        .is_err());
+    }
+}